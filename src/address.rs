@@ -0,0 +1,214 @@
+use fawkes_crypto::{
+    ff_uint::{Num, NumRepr, PrimeField, Uint},
+    native::{ecc::EdwardsPoint, poseidon::poseidon},
+    rand::Rng,
+};
+use libzeropool::native::{params::PoolParams, tx};
+use sha2::{Digest, Sha256};
+
+/// Version of the address format produced by this library, stored as the first payload byte.
+pub const ADDRESS_VERSION: u8 = 0;
+/// Number of bytes reserved for the diversifier in the address payload.
+pub const D_SIZE: usize = 10;
+const PK_D_SIZE: usize = 32;
+const CHECKSUM_SIZE: usize = 4;
+/// Unversioned addresses, parsed as version 0.
+const LEGACY_ADDRESS_SIZE: usize = D_SIZE + PK_D_SIZE + CHECKSUM_SIZE;
+const ADDRESS_SIZE: usize = 1 + LEGACY_ADDRESS_SIZE;
+
+#[derive(Debug)]
+pub enum AddressError {
+    DiversifierTooLarge,
+    Base58(bs58::decode::Error),
+    InvalidLength(usize),
+    InvalidChecksum,
+    InvalidPkD,
+    UnsupportedVersion(u8),
+}
+
+impl std::error::Error for AddressError {}
+
+impl std::fmt::Display for AddressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressError::DiversifierTooLarge => {
+                write!(f, "Diversifier does not fit in {} bytes", D_SIZE)
+            }
+            AddressError::Base58(err) => write!(f, "Invalid base58: {}", err),
+            AddressError::InvalidLength(len) => write!(f, "Invalid address length: {} bytes", len),
+            AddressError::InvalidChecksum => write!(f, "Invalid address checksum"),
+            AddressError::InvalidPkD => write!(f, "pk_d is not a valid subgroup point"),
+            AddressError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported address version: {}", version)
+            }
+        }
+    }
+}
+
+/// Samples a random diversifier that fits in the address payload.
+pub fn gen_diversifier<Fr: PrimeField, R: Rng>(rng: &mut R) -> Num<Fr> {
+    let mut bytes = [0; D_SIZE];
+    rng.fill_bytes(&mut bytes);
+
+    Num::from_uint_reduced(NumRepr(Uint::from_big_endian(&bytes)))
+}
+
+/// Derives the `index`-th diversifier of an account as `poseidon([dk, index])` truncated to
+/// its lowest `D_SIZE` bytes. The result is reproducible from the seed, while addresses at
+/// different indices still can't be linked without `dk`.
+pub fn diversifier_at_index<P: PoolParams>(dk: Num<P::Fs>, index: u32, params: &P) -> Num<P::Fr> {
+    let hash = poseidon(
+        &[dk.to_other_reduced(), Num::from(index)],
+        params.compress(),
+    );
+    let be = hash.to_uint().0.to_big_endian();
+
+    Num::from_uint_reduced(NumRepr(Uint::from_big_endian(&be[be.len() - D_SIZE..])))
+}
+
+/// Parses a big-endian diversifier, rejecting values that need more than `D_SIZE` bytes.
+pub fn diversifier_from_bytes<Fr: PrimeField>(bytes: &[u8]) -> Result<Num<Fr>, AddressError> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    let significant = &bytes[start..];
+
+    if significant.len() > D_SIZE {
+        return Err(AddressError::DiversifierTooLarge);
+    }
+
+    Ok(Num::from_uint_reduced(NumRepr(Uint::from_big_endian(
+        significant,
+    ))))
+}
+
+fn diversifier_to_bytes<Fr: PrimeField>(d: Num<Fr>) -> Result<[u8; D_SIZE], AddressError> {
+    let be = d.to_uint().0.to_big_endian();
+    let (high, low) = be.split_at(be.len() - D_SIZE);
+
+    if high.iter().any(|b| *b != 0) {
+        return Err(AddressError::DiversifierTooLarge);
+    }
+
+    let mut res = [0; D_SIZE];
+    res.copy_from_slice(low);
+
+    Ok(res)
+}
+
+pub fn derive_address<P: PoolParams>(
+    d: Num<P::Fr>,
+    dk: Num<P::Fs>,
+    params: &P,
+) -> Result<String, AddressError> {
+    let d_bytes = diversifier_to_bytes(d)?;
+    let pk_d = tx::derive_key_pk_d(d, dk, params);
+
+    Ok(encode(&d_bytes, pk_d.x))
+}
+
+/// Encodes an externally derived diversifier and big-endian `pk_d` into an address, applying
+/// the same checks as `parse_address` so that the result is always parseable.
+pub fn encode_address<P: PoolParams>(
+    d: Num<P::Fr>,
+    pk_d: &[u8],
+    params: &P,
+) -> Result<String, AddressError> {
+    let d_bytes = diversifier_to_bytes(d)?;
+    let pk_d = pk_d_from_bytes(pk_d, params)?;
+
+    Ok(encode(&d_bytes, pk_d))
+}
+
+/// Parses a big-endian `pk_d`, which must be a canonical field element of a subgroup point.
+pub fn pk_d_from_bytes<P: PoolParams>(
+    bytes: &[u8],
+    params: &P,
+) -> Result<Num<P::Fr>, AddressError> {
+    if bytes.len() != PK_D_SIZE {
+        return Err(AddressError::InvalidPkD);
+    }
+
+    let pk_d =
+        Num::from_uint(NumRepr(Uint::from_big_endian(bytes))).ok_or(AddressError::InvalidPkD)?;
+    EdwardsPoint::subgroup_decompress(pk_d, params.jubjub()).ok_or(AddressError::InvalidPkD)?;
+
+    Ok(pk_d)
+}
+
+/// Decodes an address into its diversifier and `pk_d`.
+///
+/// The payload starts with a version byte covered by the checksum. Addresses without it
+/// predate versioning and are parsed as version 0.
+///
+/// `pk_d` must be a canonical field element that decompresses to a point of the prime order
+/// subgroup, otherwise encrypting to this address would be unsafe.
+pub fn parse_address<P: PoolParams>(
+    address: &str,
+    params: &P,
+) -> Result<(Num<P::Fr>, Num<P::Fr>), AddressError> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(AddressError::Base58)?;
+
+    if bytes.len() != ADDRESS_SIZE && bytes.len() != LEGACY_ADDRESS_SIZE {
+        return Err(AddressError::InvalidLength(bytes.len()));
+    }
+
+    let (payload, hash) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if checksum(payload) != hash {
+        return Err(AddressError::InvalidChecksum);
+    }
+
+    if bytes.len() == LEGACY_ADDRESS_SIZE {
+        return parse_v0(payload, params);
+    }
+
+    match payload[0] {
+        0 => parse_v0(&payload[1..], params),
+        version => Err(AddressError::UnsupportedVersion(version)),
+    }
+}
+
+fn parse_v0<P: PoolParams>(
+    payload: &[u8],
+    params: &P,
+) -> Result<(Num<P::Fr>, Num<P::Fr>), AddressError> {
+    let d = Num::from_uint_reduced(NumRepr(Uint::from_big_endian(&payload[0..D_SIZE])));
+    let pk_d = pk_d_from_bytes(&payload[D_SIZE..], params)?;
+
+    Ok((d, pk_d))
+}
+
+/// Checks whether `address` was derived from the viewing key `dk`.
+pub fn is_own_address<P: PoolParams>(
+    address: &str,
+    dk: Num<P::Fs>,
+    params: &P,
+) -> Result<bool, AddressError> {
+    let (d, pk_d) = parse_address(address, params)?;
+
+    Ok(tx::derive_key_pk_d(d, dk, params).x == pk_d)
+}
+
+fn encode<Fr: PrimeField>(d_bytes: &[u8; D_SIZE], pk_d: Num<Fr>) -> String {
+    let mut buf: Vec<u8> = Vec::with_capacity(ADDRESS_SIZE);
+
+    buf.push(ADDRESS_VERSION);
+    buf.extend_from_slice(d_bytes);
+    buf.extend_from_slice(&pk_d.to_uint().0.to_big_endian()); // 32 bytes
+
+    let hash = checksum(&buf);
+    buf.extend_from_slice(&hash);
+
+    bs58::encode(buf).into_string()
+}
+
+fn checksum(payload: &[u8]) -> [u8; CHECKSUM_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(payload);
+    let hash = hasher.finalize();
+
+    let mut res = [0; CHECKSUM_SIZE];
+    res.copy_from_slice(&hash[0..CHECKSUM_SIZE]);
+
+    res
+}
@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wasm_bindgen::JsValue;
+
+use crate::{address::AddressError, payment::PaymentUriError};
+
+static VERBOSE: AtomicBool = AtomicBool::new(cfg!(debug_assertions));
+
+/// Errors of the wasm entry points that don't belong to a specific module.
+#[derive(Debug)]
+pub enum CommonError {
+    UnknownVerbosity(String),
+    InvalidKeyLength(usize),
+    IndexOverflow,
+    RangeTooLarge(u32),
+    TreeHeightMismatch { expected: u32, actual: u32 },
+}
+
+impl std::error::Error for CommonError {}
+
+impl std::fmt::Display for CommonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommonError::UnknownVerbosity(level) => write!(f, "Unknown error verbosity: {}", level),
+            CommonError::InvalidKeyLength(len) => {
+                write!(f, "Viewing key is longer than 32 bytes: {} bytes", len)
+            }
+            CommonError::IndexOverflow => write!(f, "Index range overflows u32"),
+            CommonError::RangeTooLarge(count) => write!(
+                f,
+                "Range of {} addresses exceeds the maximum of {}",
+                count,
+                crate::MAX_ADDRESS_RANGE
+            ),
+            CommonError::TreeHeightMismatch { expected, actual } => write!(
+                f,
+                "Tree height mismatch: expected {}, compiled with {}",
+                expected, actual
+            ),
+        }
+    }
+}
+
+/// Errors surfaced to JS, identified by a stable code that is safe to show to end users.
+pub trait ErrorCode: std::fmt::Display {
+    fn code(&self) -> &'static str;
+}
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+/// Converts an error into a JS value. Terse mode only returns the error code, verbose mode
+/// appends the underlying message, which may contain details from dependencies.
+pub fn to_js<E: ErrorCode>(err: E) -> JsValue {
+    if VERBOSE.load(Ordering::Relaxed) {
+        JsValue::from(format!("{}: {}", err.code(), err))
+    } else {
+        JsValue::from(err.code())
+    }
+}
+
+impl ErrorCode for CommonError {
+    fn code(&self) -> &'static str {
+        match self {
+            CommonError::UnknownVerbosity(_) => "UnknownVerbosity",
+            CommonError::InvalidKeyLength(_) => "InvalidKeyLength",
+            CommonError::IndexOverflow => "IndexOverflow",
+            CommonError::RangeTooLarge(_) => "RangeTooLarge",
+            CommonError::TreeHeightMismatch { .. } => "TreeHeightMismatch",
+        }
+    }
+}
+
+impl ErrorCode for AddressError {
+    fn code(&self) -> &'static str {
+        match self {
+            AddressError::DiversifierTooLarge => "DiversifierTooLarge",
+            AddressError::Base58(_) => "InvalidBase58",
+            AddressError::InvalidLength(_) => "InvalidLength",
+            AddressError::InvalidChecksum => "InvalidChecksum",
+            AddressError::InvalidPkD => "InvalidPkD",
+            AddressError::UnsupportedVersion(_) => "UnsupportedVersion",
+        }
+    }
+}
+
+impl ErrorCode for PaymentUriError {
+    fn code(&self) -> &'static str {
+        match self {
+            PaymentUriError::InvalidScheme => "InvalidScheme",
+            // The address error is more specific, e.g. a bad checksum vs an invalid pk_d
+            PaymentUriError::InvalidAddress(err) => err.code(),
+            PaymentUriError::InvalidAmount => "InvalidAmount",
+            PaymentUriError::MissingAmount => "MissingAmount",
+            PaymentUriError::InvalidEncoding => "InvalidEncoding",
+            PaymentUriError::DuplicateParameter(_) => "DuplicateParameter",
+        }
+    }
+}
@@ -1,16 +1,33 @@
-use fawkes_crypto::{
-    ff_uint::{Num, NumRepr, Uint},
-    rand::Rng,
-};
+use fawkes_crypto::ff_uint::{Num, NumRepr, Uint};
+use js_sys::Array;
+#[cfg(feature = "prover")]
 use js_sys::Function;
-use libzeropool::{native::tx, POOL_PARAMS};
+use libzeropool::{
+    constants,
+    native::params::{PoolBN256, PoolParams},
+    POOL_PARAMS,
+};
 use sha2::{Digest, Sha256};
 use wasm_bindgen::prelude::*;
 use web_sys::Performance;
 
+pub mod address;
+mod errors;
+mod payment;
 mod random;
 mod utils;
 
+pub use payment::PaymentRequest;
+
+type Fr = <PoolBN256 as PoolParams>::Fr;
+type Fs = <PoolBN256 as PoolParams>::Fs;
+
+const FINGERPRINT_SIZE: usize = 8;
+const DK_SIZE: usize = 32;
+/// Largest `count` accepted by `deriveAddressRange`, every address costs a Poseidon hash and a
+/// scalar multiplication on the calling thread.
+pub const MAX_ADDRESS_RANGE: u32 = 1000;
+
 pub struct Timer {
     start: f64,
     perf: Performance,
@@ -26,7 +43,11 @@ impl Timer {
     }
 
     pub fn elapsed_s(&self) -> f64 {
-        (self.perf.now() - self.start) / 1000.0
+        self.elapsed_ms() / 1000.0
+    }
+
+    pub fn elapsed_ms(&self) -> f64 {
+        self.perf.now() - self.start
     }
 }
 
@@ -36,28 +57,293 @@ impl Timer {
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+// Runs when the module is instantiated, so that every build, including scan-only ones, gets
+// readable panic messages.
+#[wasm_bindgen(start)]
+pub fn start() {
+    utils::set_panic_hook();
+}
+
+/// Sets how much detail errors carry: `"terse"` returns only the error code, `"verbose"` also
+/// includes the underlying message. Defaults to terse in release builds.
+#[wasm_bindgen(js_name = setErrorVerbosity)]
+pub fn set_error_verbosity(level: &str) -> Result<(), JsValue> {
+    match level {
+        "terse" => errors::set_verbose(false),
+        "verbose" => errors::set_verbose(true),
+        _ => {
+            return Err(errors::to_js(errors::CommonError::UnknownVerbosity(
+                level.to_owned(),
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a big-endian viewing key. Longer inputs would overflow the field representation.
+fn dk_from_bytes(dk: &[u8]) -> Result<Num<Fs>, errors::CommonError> {
+    if dk.len() > DK_SIZE {
+        return Err(errors::CommonError::InvalidKeyLength(dk.len()));
+    }
+
+    Ok(Num::from_uint_reduced(NumRepr(Uint::from_big_endian(dk))))
+}
+
 #[wasm_bindgen(js_name = deriveAddress)]
 pub fn derive_address(dk: &[u8]) -> Result<String, JsValue> {
     let mut rng = random::CustomRng;
-    let d = rng.gen();
-    let dk = Num::from_uint_reduced(NumRepr(Uint::from_big_endian(dk)));
-    let pk_d = tx::derive_key_pk_d(d, dk, &*POOL_PARAMS);
-    let mut buf: Vec<u8> = Vec::with_capacity(48);
+    let d = address::gen_diversifier(&mut rng);
+    let dk = dk_from_bytes(dk).map_err(errors::to_js)?;
+
+    address::derive_address(d, dk, &*POOL_PARAMS).map_err(errors::to_js)
+}
 
-    buf.extend_from_slice(&d.to_uint().0.to_big_endian()[0..10]);
-    buf.extend_from_slice(&pk_d.x.to_uint().0.to_big_endian()); // 32 bytes
+#[wasm_bindgen(js_name = deriveAddressAt)]
+pub fn derive_address_at(dk: &[u8], d: &[u8]) -> Result<String, JsValue> {
+    let d = address::diversifier_from_bytes(d).map_err(errors::to_js)?;
+    let dk = dk_from_bytes(dk).map_err(errors::to_js)?;
+
+    address::derive_address(d, dk, &*POOL_PARAMS).map_err(errors::to_js)
+}
 
-    let mut hasher = Sha256::new();
-    hasher.update(&buf);
-    let hash = hasher.finalize();
+#[wasm_bindgen(js_name = deriveAddressAtIndex)]
+pub fn derive_address_at_index(dk: &[u8], index: u32) -> Result<String, JsValue> {
+    let dk = dk_from_bytes(dk).map_err(errors::to_js)?;
+    let d = address::diversifier_at_index(dk, index, &*POOL_PARAMS);
 
-    buf.extend_from_slice(&hash[0..4]);
+    address::derive_address(d, dk, &*POOL_PARAMS).map_err(errors::to_js)
+}
+
+/// Derives the addresses at indices `start..start + count`, e.g. to precompute a gap-limit set.
+/// `count` is limited to `MAX_ADDRESS_RANGE`.
+#[wasm_bindgen(js_name = deriveAddressRange)]
+pub fn derive_address_range(dk: &[u8], start: u32, count: u32) -> Result<Array, JsValue> {
+    if count > MAX_ADDRESS_RANGE {
+        return Err(errors::to_js(errors::CommonError::RangeTooLarge(count)));
+    }
+
+    if count == 0 {
+        return Ok(Array::new());
+    }
+
+    // Inclusive, so that the range can end at `u32::MAX`
+    let last = start
+        .checked_add(count - 1)
+        .ok_or_else(|| errors::to_js(errors::CommonError::IndexOverflow))?;
+
+    (start..=last)
+        .map(|index| derive_address_at_index(dk, index).map(JsValue::from))
+        .collect()
+}
+
+#[wasm_bindgen(js_name = encodeAddress)]
+pub fn encode_address(d: &[u8], pk_d: &[u8]) -> Result<String, JsValue> {
+    let d = address::diversifier_from_bytes(d).map_err(errors::to_js)?;
+
+    address::encode_address(d, pk_d, &*POOL_PARAMS).map_err(errors::to_js)
+}
+
+/// Returns true if both addresses are diversified forms of the account with viewing key `dk`.
+#[wasm_bindgen(js_name = addressesLinked)]
+pub fn addresses_linked(dk: &[u8], a: &str, b: &str) -> Result<bool, JsValue> {
+    let dk = dk_from_bytes(dk).map_err(errors::to_js)?;
+
+    let a_owned = address::is_own_address(a, dk, &*POOL_PARAMS).map_err(errors::to_js)?;
+    let b_owned = address::is_own_address(b, dk, &*POOL_PARAMS).map_err(errors::to_js)?;
+
+    Ok(a_owned && b_owned)
+}
+
+/// Short hex fingerprint of the viewing key, so that two parties can check they hold the same
+/// `dk` without exchanging it.
+#[wasm_bindgen(js_name = viewingKeyFingerprint)]
+pub fn viewing_key_fingerprint(dk: &[u8]) -> Result<String, JsValue> {
+    let dk = dk_from_bytes(dk).map_err(errors::to_js)?;
+    let hash = Sha256::digest(&dk.to_uint().0.to_big_endian());
+
+    Ok(hash[0..FINGERPRINT_SIZE]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Height of the commitment tree this build was compiled with.
+#[wasm_bindgen(js_name = treeHeight)]
+pub fn tree_height() -> u32 {
+    constants::HEIGHT as u32
+}
+
+/// Fails if the compiled tree height differs from the one the caller's proving keys expect.
+#[wasm_bindgen(js_name = checkTreeHeight)]
+pub fn check_tree_height(expected: u32) -> Result<(), JsValue> {
+    if expected != tree_height() {
+        return Err(errors::to_js(errors::CommonError::TreeHeightMismatch {
+            expected,
+            actual: tree_height(),
+        }));
+    }
 
-    Ok(bs58::encode(buf).into_string())
+    Ok(())
 }
 
+#[wasm_bindgen]
+pub struct ParsedAddress {
+    d: Vec<u8>,
+    pk_d: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl ParsedAddress {
+    #[wasm_bindgen(getter)]
+    pub fn d(&self) -> Vec<u8> {
+        self.d.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = pkD)]
+    pub fn pk_d(&self) -> Vec<u8> {
+        self.pk_d.clone()
+    }
+}
+
+#[wasm_bindgen(js_name = parseAddress)]
+pub fn parse_address(address: &str) -> Result<ParsedAddress, JsValue> {
+    let (d, pk_d) = address::parse_address(address, &*POOL_PARAMS).map_err(errors::to_js)?;
+
+    Ok(ParsedAddress {
+        d: d.to_uint().0.to_big_endian()[32 - address::D_SIZE..].to_vec(),
+        pk_d: pk_d.to_uint().0.to_big_endian().to_vec(),
+    })
+}
+
+#[wasm_bindgen(js_name = buildPaymentUri)]
+pub fn build_payment_uri(
+    address: String,
+    amount: String,
+    memo: Option<String>,
+) -> Result<String, JsValue> {
+    let request = PaymentRequest {
+        address,
+        amount,
+        memo,
+    };
+
+    payment::build_payment_uri(&request, &*POOL_PARAMS).map_err(errors::to_js)
+}
+
+#[wasm_bindgen(js_name = parsePaymentUri)]
+pub fn parse_payment_uri(uri: &str) -> Result<PaymentRequest, JsValue> {
+    payment::parse_payment_uri(uri, &*POOL_PARAMS).map_err(errors::to_js)
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct SelfTestReport {
+    #[wasm_bindgen(readonly, js_name = addressDerivation)]
+    pub address_derivation: bool,
+    #[wasm_bindgen(readonly, js_name = addressRoundtrip)]
+    pub address_roundtrip: bool,
+    #[wasm_bindgen(readonly, js_name = merkleProof)]
+    pub merkle_proof: bool,
+}
+
+#[wasm_bindgen]
+impl SelfTestReport {
+    #[wasm_bindgen(getter)]
+    pub fn passed(&self) -> bool {
+        self.address_derivation && self.address_roundtrip && self.merkle_proof
+    }
+
+    /// Steps of the self-test that this build can't run, `passed` doesn't cover them.
+    #[wasm_bindgen(getter, js_name = skippedSteps)]
+    pub fn skipped_steps(&self) -> Array {
+        std::iter::once(JsValue::from("noteEncryption")).collect()
+    }
+}
+
+/// Smoke test for integrators: derives an address from a random key, parses it back and checks
+/// a two level merkle proof against roots hashed level by level.
+///
+/// Note encryption is not checked: this crate doesn't expose the note cypher yet, so the step is
+/// reported in `skippedSteps` instead.
+#[wasm_bindgen(js_name = selfTest)]
+pub fn self_test() -> SelfTestReport {
+    use fawkes_crypto::{
+        core::sizedvec::SizedVec,
+        native::poseidon::{poseidon, poseidon_merkle_proof_root, MerkleProof},
+        rand::Rng,
+    };
+
+    let mut rng = random::CustomRng;
+
+    let dk: Num<Fs> = rng.gen();
+    let d = address::gen_diversifier(&mut rng);
+    let derived = address::derive_address(d, dk, &*POOL_PARAMS).ok();
+
+    let address_roundtrip = derived
+        .as_ref()
+        .and_then(|addr| {
+            let (parsed_d, _) = address::parse_address(addr, &*POOL_PARAMS).ok()?;
+            let own = address::is_own_address(addr, dk, &*POOL_PARAMS).ok()?;
+
+            Some(parsed_d == d && own)
+        })
+        .unwrap_or(false);
+
+    // Fixed inputs with mixed path bits, so that a swapped hash order or an ignored path bit
+    // changes the root
+    let compress = POOL_PARAMS.compress();
+    let leaf = Num::<Fr>::from(1u32);
+    let siblings = [Num::<Fr>::from(2u32), Num::<Fr>::from(3u32)];
+    let proof_root = |path: [bool; 2]| {
+        let proof = MerkleProof::<Fr, 2> {
+            sibling: siblings.iter().cloned().collect::<SizedVec<_, 2>>(),
+            path: path.iter().cloned().collect::<SizedVec<_, 2>>(),
+        };
+
+        poseidon_merkle_proof_root(leaf, &proof, compress)
+    };
+
+    // A set path bit means that the node is the right child
+    let node = poseidon(&[siblings[0], leaf], compress);
+    let expected_root = poseidon(&[node, siblings[1]], compress);
+    let root = proof_root([true, false]);
+
+    SelfTestReport {
+        address_derivation: derived.is_some(),
+        address_roundtrip,
+        merkle_proof: root == expected_root && proof_root([false, true]) != root,
+    }
+}
+
+/// Whether this build includes proving, see the `prover` feature.
+#[wasm_bindgen(js_name = hasProver)]
+pub fn has_prover() -> bool {
+    cfg!(feature = "prover")
+}
+
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct PoseidonMerkleRootResult {
+    #[wasm_bindgen(readonly, js_name = setupMs)]
+    pub setup_ms: f64,
+    #[wasm_bindgen(readonly, js_name = initMs)]
+    pub init_ms: f64,
+    #[wasm_bindgen(readonly, js_name = proveMs)]
+    pub prove_ms: f64,
+    #[wasm_bindgen(readonly, js_name = verifyMs)]
+    pub verify_ms: f64,
+    #[wasm_bindgen(readonly)]
+    pub verified: bool,
+}
+
+/// Runs setup, proving and verification of a Poseidon merkle root circuit and reports the time
+/// spent in each step. The optional callback is called after each step with its name and
+/// duration in seconds.
+#[cfg(feature = "prover")]
 #[wasm_bindgen(js_name = testPoseidonMerkleRoot)]
-pub fn test_circuit_poseidon_merkle_root(callback: Function) {
+pub fn test_circuit_poseidon_merkle_root(callback: Option<Function>) -> PoseidonMerkleRootResult {
     use fawkes_crypto::backend::bellman_groth16::engines::Bn256;
     use fawkes_crypto::backend::bellman_groth16::{prover, setup, verifier};
     use fawkes_crypto::circuit::num::CNum;
@@ -69,16 +355,18 @@ pub fn test_circuit_poseidon_merkle_root(callback: Function) {
     use fawkes_crypto::native::poseidon::{
         poseidon_merkle_proof_root, MerkleProof, PoseidonParams,
     };
+    use fawkes_crypto::rand::Rng;
 
     macro_rules! log_js {
         ($func:expr, $text:expr, $time:expr) => {{
-            $func
-                .call2(
+            if let Some(func) = &$func {
+                func.call2(
                     &JsValue::NULL,
                     &JsValue::from($text),
                     &JsValue::from($time.elapsed_s()),
                 )
                 .unwrap();
+            }
         }};
     }
 
@@ -88,10 +376,9 @@ pub fn test_circuit_poseidon_merkle_root(callback: Function) {
         res.assert_eq(&public);
     }
 
-    utils::set_panic_hook();
-
     let time = Timer::now();
     let params = setup::setup::<Bn256, _, _, _>(circuit);
+    let setup_ms = time.elapsed_ms();
     log_js!(callback, "Setup", time);
 
     let time = Timer::now();
@@ -108,15 +395,24 @@ pub fn test_circuit_poseidon_merkle_root(callback: Function) {
         .collect::<SizedVec<bool, PROOF_LENGTH>>();
     let proof = MerkleProof { sibling, path };
     let root = poseidon_merkle_proof_root(leaf, &proof, &poseidon_params);
+    let init_ms = time.elapsed_ms();
     log_js!(callback, "Merkle tree init", time);
 
     let time = Timer::now();
     let (inputs, snark_proof) = prover::prove(&params, &root, &(leaf, proof), circuit);
+    let prove_ms = time.elapsed_ms();
     log_js!(callback, "Prove", time);
 
     let time = Timer::now();
-    let res = verifier::verify(&params.get_vk(), &snark_proof, &inputs);
+    let verified = verifier::verify(&params.get_vk(), &snark_proof, &inputs);
+    let verify_ms = time.elapsed_ms();
     log_js!(callback, "Verify", time);
 
-    assert!(res, "Verifier result should be true");
+    PoseidonMerkleRootResult {
+        setup_ms,
+        init_ms,
+        prove_ms,
+        verify_ms,
+        verified,
+    }
 }
@@ -0,0 +1,188 @@
+use libzeropool::native::params::PoolParams;
+use wasm_bindgen::prelude::*;
+
+use crate::address::{self, AddressError};
+
+pub const URI_SCHEME: &str = "zeropool:";
+
+#[derive(Debug)]
+pub enum PaymentUriError {
+    InvalidScheme,
+    InvalidAddress(AddressError),
+    InvalidAmount,
+    MissingAmount,
+    InvalidEncoding,
+    DuplicateParameter(String),
+}
+
+impl std::error::Error for PaymentUriError {}
+
+impl std::fmt::Display for PaymentUriError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PaymentUriError::InvalidScheme => write!(f, "URI must start with {}", URI_SCHEME),
+            PaymentUriError::InvalidAddress(err) => write!(f, "Invalid address: {}", err),
+            PaymentUriError::InvalidAmount => write!(f, "Amount must be a decimal integer"),
+            PaymentUriError::MissingAmount => write!(f, "Missing amount"),
+            PaymentUriError::InvalidEncoding => write!(f, "Invalid percent-encoding"),
+            PaymentUriError::DuplicateParameter(key) => write!(f, "Duplicate parameter: {}", key),
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub struct PaymentRequest {
+    pub(crate) address: String,
+    pub(crate) amount: String,
+    pub(crate) memo: Option<String>,
+}
+
+#[wasm_bindgen]
+impl PaymentRequest {
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn amount(&self) -> String {
+        self.amount.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn memo(&self) -> Option<String> {
+        self.memo.clone()
+    }
+}
+
+/// Builds a `zeropool:<address>?amount=<amount>[&memo=<memo>]` URI with percent-encoded
+/// parameter values.
+pub fn build_payment_uri<P: PoolParams>(
+    request: &PaymentRequest,
+    params: &P,
+) -> Result<String, PaymentUriError> {
+    address::parse_address(&request.address, params).map_err(PaymentUriError::InvalidAddress)?;
+    validate_amount(&request.amount)?;
+
+    let mut uri = format!(
+        "{}{}?amount={}",
+        URI_SCHEME,
+        request.address,
+        percent_encode(&request.amount)
+    );
+
+    if let Some(memo) = &request.memo {
+        uri.push_str("&memo=");
+        uri.push_str(&percent_encode(memo));
+    }
+
+    Ok(uri)
+}
+
+/// Parses a URI produced by `build_payment_uri`. Unknown parameters and the fragment are
+/// ignored, repeated `amount` or `memo` parameters are rejected so that the request can't be
+/// read differently by different parsers.
+pub fn parse_payment_uri<P: PoolParams>(
+    uri: &str,
+    params: &P,
+) -> Result<PaymentRequest, PaymentUriError> {
+    // URI schemes are case-insensitive (RFC 3986, section 3.1)
+    let has_scheme = uri
+        .get(..URI_SCHEME.len())
+        .map_or(false, |scheme| scheme.eq_ignore_ascii_case(URI_SCHEME));
+
+    if !has_scheme {
+        return Err(PaymentUriError::InvalidScheme);
+    }
+
+    let rest = &uri[URI_SCHEME.len()..];
+    let rest = match rest.find('#') {
+        Some(pos) => &rest[..pos],
+        None => rest,
+    };
+    let (address, query) = match rest.find('?') {
+        Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+        None => (rest, ""),
+    };
+
+    address::parse_address(address, params).map_err(PaymentUriError::InvalidAddress)?;
+
+    let mut amount = None;
+    let mut memo = None;
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = match pair.find('=') {
+            Some(pos) => (&pair[..pos], &pair[pos + 1..]),
+            None => (pair, ""),
+        };
+
+        let field = match key {
+            "amount" => &mut amount,
+            "memo" => &mut memo,
+            _ => continue,
+        };
+
+        if field.is_some() {
+            return Err(PaymentUriError::DuplicateParameter(key.to_owned()));
+        }
+
+        *field = Some(percent_decode(value)?);
+    }
+
+    let amount = amount.ok_or(PaymentUriError::MissingAmount)?;
+    validate_amount(&amount)?;
+
+    Ok(PaymentRequest {
+        address: address.to_owned(),
+        amount,
+        memo,
+    })
+}
+
+fn validate_amount(amount: &str) -> Result<(), PaymentUriError> {
+    if amount.is_empty() || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(PaymentUriError::InvalidAmount);
+    }
+
+    Ok(())
+}
+
+fn percent_encode(value: &str) -> String {
+    let mut res = String::with_capacity(value.len());
+
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                res.push(b as char)
+            }
+            _ => res.push_str(&format!("%{:02X}", b)),
+        }
+    }
+
+    res
+}
+
+fn percent_decode(value: &str) -> Result<String, PaymentUriError> {
+    let bytes = value.as_bytes();
+    let mut res = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .ok_or(PaymentUriError::InvalidEncoding)?;
+            let b = u8::from_str_radix(hex, 16).map_err(|_| PaymentUriError::InvalidEncoding)?;
+
+            res.push(b);
+            i += 3;
+        } else {
+            res.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(res).map_err(|_| PaymentUriError::InvalidEncoding)
+}
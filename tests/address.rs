@@ -0,0 +1,117 @@
+//! Robustness tests for address parsing, run natively with `cargo test`.
+//!
+//! Inputs come from a seeded generator. The seed is printed by every test and can be fixed with
+//! `ADDRESS_FUZZ_SEED=<seed> cargo test` to reproduce a failure.
+
+use std::panic::{self, AssertUnwindSafe};
+
+use getrandom::getrandom;
+use libzeropool::POOL_PARAMS;
+use libzeropool_wasm::address;
+use sha2::{Digest, Sha256};
+
+const ITERATIONS: usize = 1000;
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// SplitMix64, small enough to keep the inputs reproducible without extra dependencies.
+struct TestRng(u64);
+
+impl TestRng {
+    fn from_env() -> TestRng {
+        let seed = match std::env::var("ADDRESS_FUZZ_SEED") {
+            Ok(seed) => seed.parse().expect("ADDRESS_FUZZ_SEED must be a u64"),
+            Err(_) => {
+                let mut buf = [0; 8];
+                getrandom(&mut buf).unwrap();
+                u64::from_le_bytes(buf)
+            }
+        };
+
+        println!("ADDRESS_FUZZ_SEED={}", seed);
+
+        TestRng(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, buf: &mut [u8]) {
+        for b in buf.iter_mut() {
+            *b = self.next_u64() as u8;
+        }
+    }
+
+    fn bytes(&mut self, max_len: usize) -> Vec<u8> {
+        let mut buf = vec![0; self.next_u64() as usize % (max_len + 1)];
+        self.fill_bytes(&mut buf);
+
+        buf
+    }
+}
+
+fn assert_no_panic(input: &str) {
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _ = address::parse_address(input, &*POOL_PARAMS);
+    }));
+
+    assert!(res.is_ok(), "parse_address panicked on input {:?}", input);
+}
+
+#[test]
+fn parse_random_base58() {
+    let mut rng = TestRng::from_env();
+
+    for _ in 0..ITERATIONS {
+        assert_no_panic(&bs58::encode(rng.bytes(64)).into_string());
+    }
+}
+
+#[test]
+fn parse_random_strings() {
+    let mut rng = TestRng::from_env();
+
+    for _ in 0..ITERATIONS {
+        let input: String = rng
+            .bytes(80)
+            .into_iter()
+            .map(|b| {
+                // Mostly valid base58 characters, with the occasional invalid byte
+                if b < 240 {
+                    BASE58_ALPHABET[b as usize % BASE58_ALPHABET.len()] as char
+                } else {
+                    b as char
+                }
+            })
+            .collect();
+
+        assert_no_panic(&input);
+    }
+}
+
+#[test]
+fn parse_random_payload_with_valid_checksum() {
+    let mut rng = TestRng::from_env();
+
+    for _ in 0..ITERATIONS {
+        // Unversioned and versioned payloads, the version byte being either 0 or unsupported
+        for len in [42, 43].iter() {
+            let mut buf = vec![0; *len];
+            rng.fill_bytes(&mut buf);
+
+            if *len == 43 {
+                buf[0] %= 2;
+            }
+
+            let hash = Sha256::digest(&buf);
+            buf.extend_from_slice(&hash[0..4]);
+
+            assert_no_panic(&bs58::encode(buf).into_string());
+        }
+    }
+}
@@ -14,3 +14,340 @@ fn pass() {
     let result = libzeropool_wasm::derive_address(b"12300000000000000000000000000000");
     assert!(result.is_ok());
 }
+
+#[wasm_bindgen_test]
+fn derive_address_at_explicit_diversifier() {
+    let dk = b"12300000000000000000000000000000";
+
+    assert!(libzeropool_wasm::derive_address_at(dk, &[0xff; 10]).is_ok());
+    assert!(libzeropool_wasm::derive_address_at(dk, &[0, 0, 0xff]).is_ok());
+    assert!(libzeropool_wasm::derive_address_at(dk, &[0x01; 11]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn parse_address_roundtrip() {
+    let dk = b"12300000000000000000000000000000";
+    let d = [0x11; 10];
+    let address = libzeropool_wasm::derive_address_at(dk, &d).unwrap();
+    let parsed = libzeropool_wasm::parse_address(&address).unwrap();
+
+    assert_eq!(parsed.d(), d.to_vec());
+}
+
+#[wasm_bindgen_test]
+fn parse_address_rejects_invalid_pk_d() {
+    use sha2::{Digest, Sha256};
+
+    // x = 3 has no corresponding y on the curve
+    let mut pk_d = [0; 32];
+    pk_d[31] = 3;
+
+    let mut buf = vec![0x11; 10];
+    buf.extend_from_slice(&pk_d);
+    let hash = Sha256::digest(&buf);
+    buf.extend_from_slice(&hash[0..4]);
+
+    let address = bs58::encode(buf).into_string();
+    assert!(libzeropool_wasm::parse_address(&address).is_err());
+
+    // Not a canonical field element
+    let mut buf = vec![0x11; 10];
+    buf.extend_from_slice(&[0xff; 32]);
+    let hash = Sha256::digest(&buf);
+    buf.extend_from_slice(&hash[0..4]);
+
+    let address = bs58::encode(buf).into_string();
+    assert!(libzeropool_wasm::parse_address(&address).is_err());
+}
+
+#[wasm_bindgen_test]
+fn derive_address_with_custom_params() {
+    use fawkes_crypto::{
+        ff_uint::{Num, NumRepr, Uint},
+        native::poseidon::PoseidonParams,
+    };
+    use libzeropool::POOL_PARAMS;
+    use libzeropool_wasm::address;
+
+    let mut params = (*POOL_PARAMS).clone();
+    params.hash = PoseidonParams::new(2, 8, 54);
+
+    let d = address::diversifier_from_bytes(&[0x11; 10]).unwrap();
+    let dk = Num::from_uint_reduced(NumRepr(Uint::from_big_endian(
+        b"12300000000000000000000000000000",
+    )));
+
+    let default_address = address::derive_address(d, dk, &*POOL_PARAMS).unwrap();
+    let custom_address = address::derive_address(d, dk, &params).unwrap();
+
+    assert_ne!(default_address, custom_address);
+    assert!(address::parse_address(&custom_address, &params).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn viewing_key_fingerprint() {
+    let fingerprint =
+        libzeropool_wasm::viewing_key_fingerprint(b"12300000000000000000000000000000").unwrap();
+
+    assert_eq!(fingerprint.len(), 16);
+    assert_eq!(
+        fingerprint,
+        libzeropool_wasm::viewing_key_fingerprint(b"12300000000000000000000000000000").unwrap()
+    );
+    assert_ne!(
+        fingerprint,
+        libzeropool_wasm::viewing_key_fingerprint(b"45600000000000000000000000000000").unwrap()
+    );
+}
+
+#[wasm_bindgen_test]
+fn check_tree_height() {
+    let height = libzeropool_wasm::tree_height();
+
+    assert!(libzeropool_wasm::check_tree_height(height).is_ok());
+    assert!(libzeropool_wasm::check_tree_height(height + 1).is_err());
+}
+
+#[wasm_bindgen_test]
+fn derive_address_at_index_is_deterministic() {
+    let dk = b"12300000000000000000000000000000";
+    let address = libzeropool_wasm::derive_address_at_index(dk, 5).unwrap();
+
+    assert_eq!(
+        address,
+        libzeropool_wasm::derive_address_at_index(dk, 5).unwrap()
+    );
+    assert_ne!(
+        address,
+        libzeropool_wasm::derive_address_at_index(dk, 6).unwrap()
+    );
+    assert!(libzeropool_wasm::parse_address(&address).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn encode_address_matches_derived() {
+    let dk = b"12300000000000000000000000000000";
+    let address = libzeropool_wasm::derive_address_at(dk, &[0x11; 10]).unwrap();
+    let parsed = libzeropool_wasm::parse_address(&address).unwrap();
+
+    assert_eq!(
+        libzeropool_wasm::encode_address(&parsed.d(), &parsed.pk_d()).unwrap(),
+        address
+    );
+    assert!(libzeropool_wasm::encode_address(&parsed.d(), &parsed.pk_d()[1..]).is_err());
+    assert!(libzeropool_wasm::encode_address(&[0x11; 11], &parsed.pk_d()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn payment_uri_roundtrip() {
+    let address = libzeropool_wasm::derive_address(b"12300000000000000000000000000000").unwrap();
+
+    let uri =
+        libzeropool_wasm::build_payment_uri(address.clone(), "1000".to_owned(), None).unwrap();
+    let request = libzeropool_wasm::parse_payment_uri(&uri).unwrap();
+    assert_eq!(request.address(), address);
+    assert_eq!(request.amount(), "1000");
+    assert_eq!(request.memo(), None);
+
+    let memo = "Invoice #42 & co, 100%".to_owned();
+    let uri =
+        libzeropool_wasm::build_payment_uri(address.clone(), "5".to_owned(), Some(memo.clone()))
+            .unwrap();
+    let request = libzeropool_wasm::parse_payment_uri(&uri).unwrap();
+    assert_eq!(request.address(), address);
+    assert_eq!(request.amount(), "5");
+    assert_eq!(request.memo(), Some(memo));
+
+    let uri = format!("ZeroPool:{}?amount=7", address);
+    let request = libzeropool_wasm::parse_payment_uri(&uri).unwrap();
+    assert_eq!(request.address(), address);
+    assert_eq!(request.amount(), "7");
+
+    let uri = format!("zeropool:{}?amount=8&memo=a#fragment", address);
+    let request = libzeropool_wasm::parse_payment_uri(&uri).unwrap();
+    assert_eq!(request.address(), address);
+    assert_eq!(request.amount(), "8");
+    assert_eq!(request.memo(), Some("a".to_owned()));
+
+    let uri = format!("zeropool:{}#fragment?amount=9", address);
+    assert!(libzeropool_wasm::parse_payment_uri(&uri).is_err());
+}
+
+#[wasm_bindgen_test]
+fn payment_uri_rejects_invalid_input() {
+    let address = libzeropool_wasm::derive_address(b"12300000000000000000000000000000").unwrap();
+
+    assert!(
+        libzeropool_wasm::build_payment_uri("invalid".to_owned(), "1".to_owned(), None).is_err()
+    );
+    assert!(libzeropool_wasm::build_payment_uri(address.clone(), "1.5".to_owned(), None).is_err());
+    assert!(libzeropool_wasm::parse_payment_uri(&format!("bitcoin:{}?amount=1", address)).is_err());
+    assert!(libzeropool_wasm::parse_payment_uri(&format!("zeropool:{}", address)).is_err());
+    assert!(libzeropool_wasm::parse_payment_uri(&format!(
+        "zeropool:{}?amount=1&memo=%zz",
+        address
+    ))
+    .is_err());
+
+    libzeropool_wasm::set_error_verbosity("terse").unwrap();
+    for query in ["amount=1&amount=1000", "amount=1&memo=a&memo=b"].iter() {
+        let err = libzeropool_wasm::parse_payment_uri(&format!("zeropool:{}?{}", address, query))
+            .err()
+            .unwrap();
+        assert_eq!(err.as_string().unwrap(), "DuplicateParameter");
+    }
+}
+
+#[wasm_bindgen_test]
+fn addresses_linked() {
+    let dk = b"12300000000000000000000000000000";
+    let a = libzeropool_wasm::derive_address(dk).unwrap();
+    let b = libzeropool_wasm::derive_address(dk).unwrap();
+    let foreign = libzeropool_wasm::derive_address(b"45600000000000000000000000000000").unwrap();
+
+    assert!(libzeropool_wasm::addresses_linked(dk, &a, &b).unwrap());
+    assert!(!libzeropool_wasm::addresses_linked(dk, &a, &foreign).unwrap());
+    assert!(libzeropool_wasm::addresses_linked(dk, &a, "invalid").is_err());
+}
+
+#[wasm_bindgen_test]
+fn parse_address_versions() {
+    use sha2::{Digest, Sha256};
+
+    let dk = b"12300000000000000000000000000000";
+    let address = libzeropool_wasm::derive_address_at(dk, &[0x11; 10]).unwrap();
+    let bytes = bs58::decode(&address).into_vec().unwrap();
+    assert_eq!(bytes.len(), 47);
+    assert_eq!(bytes[0], 0);
+
+    // Unversioned addresses are parsed as version 0
+    let mut legacy = bytes[1..43].to_vec();
+    let hash = Sha256::digest(&legacy);
+    legacy.extend_from_slice(&hash[0..4]);
+
+    let parsed = libzeropool_wasm::parse_address(&bs58::encode(legacy).into_string()).unwrap();
+    assert_eq!(parsed.d(), vec![0x11; 10]);
+
+    let mut unknown = bytes[0..43].to_vec();
+    unknown[0] = 1;
+    let hash = Sha256::digest(&unknown);
+    unknown.extend_from_slice(&hash[0..4]);
+
+    assert!(libzeropool_wasm::parse_address(&bs58::encode(unknown).into_string()).is_err());
+}
+
+#[wasm_bindgen_test]
+fn derive_address_range() {
+    let dk = b"12300000000000000000000000000000";
+    let range = libzeropool_wasm::derive_address_range(dk, 5, 3).unwrap();
+
+    assert_eq!(range.length(), 3);
+    for (i, index) in (5..8).enumerate() {
+        assert_eq!(
+            range.get(i as u32).as_string().unwrap(),
+            libzeropool_wasm::derive_address_at_index(dk, index).unwrap()
+        );
+    }
+
+    let last = libzeropool_wasm::derive_address_range(dk, u32::MAX, 1).unwrap();
+    assert_eq!(
+        last.get(0).as_string().unwrap(),
+        libzeropool_wasm::derive_address_at_index(dk, u32::MAX).unwrap()
+    );
+    assert_eq!(
+        libzeropool_wasm::derive_address_range(dk, 0, 0)
+            .unwrap()
+            .length(),
+        0
+    );
+
+    libzeropool_wasm::set_error_verbosity("terse").unwrap();
+    let err = libzeropool_wasm::derive_address_range(dk, u32::MAX, 2)
+        .err()
+        .unwrap();
+    assert_eq!(err.as_string().unwrap(), "IndexOverflow");
+
+    let count = libzeropool_wasm::MAX_ADDRESS_RANGE + 1;
+    let err = libzeropool_wasm::derive_address_range(dk, 0, count)
+        .err()
+        .unwrap();
+    assert_eq!(err.as_string().unwrap(), "RangeTooLarge");
+}
+
+#[wasm_bindgen_test]
+fn error_verbosity() {
+    libzeropool_wasm::set_error_verbosity("terse").unwrap();
+    let err = libzeropool_wasm::parse_address("1111").err().unwrap();
+    assert_eq!(err.as_string().unwrap(), "InvalidLength");
+
+    libzeropool_wasm::set_error_verbosity("verbose").unwrap();
+    let err = libzeropool_wasm::parse_address("1111").err().unwrap();
+    assert!(err.as_string().unwrap().starts_with("InvalidLength: "));
+
+    libzeropool_wasm::set_error_verbosity("terse").unwrap();
+    let height = libzeropool_wasm::tree_height();
+    let err = libzeropool_wasm::check_tree_height(height + 1)
+        .err()
+        .unwrap();
+    assert_eq!(err.as_string().unwrap(), "TreeHeightMismatch");
+
+    let err = libzeropool_wasm::set_error_verbosity("loud").err().unwrap();
+    assert_eq!(err.as_string().unwrap(), "UnknownVerbosity");
+
+    // Payment URI errors expose the underlying address error code
+    let err = libzeropool_wasm::parse_payment_uri("zeropool:1111?amount=1")
+        .err()
+        .unwrap();
+    assert_eq!(err.as_string().unwrap(), "InvalidLength");
+}
+
+#[wasm_bindgen_test]
+fn rejects_long_viewing_key() {
+    libzeropool_wasm::set_error_verbosity("terse").unwrap();
+
+    let dk = [1u8; 33];
+    let addr = libzeropool_wasm::derive_address_at_index(&[1u8; 32], 0).unwrap();
+    let results = vec![
+        libzeropool_wasm::derive_address(&dk).err(),
+        libzeropool_wasm::derive_address_at(&dk, &[0; 10]).err(),
+        libzeropool_wasm::derive_address_at_index(&dk, 0).err(),
+        libzeropool_wasm::derive_address_range(&dk, 0, 1).err(),
+        libzeropool_wasm::addresses_linked(&dk, &addr, &addr).err(),
+        libzeropool_wasm::viewing_key_fingerprint(&dk).err(),
+    ];
+
+    for err in results {
+        assert_eq!(err.unwrap().as_string().unwrap(), "InvalidKeyLength");
+    }
+}
+
+#[cfg(feature = "prover")]
+#[wasm_bindgen_test]
+fn poseidon_merkle_root_result() {
+    let result = libzeropool_wasm::test_circuit_poseidon_merkle_root(None);
+
+    assert!(result.verified);
+    assert!(result.prove_ms >= 0.0);
+}
+
+#[wasm_bindgen_test]
+fn has_prover() {
+    assert_eq!(libzeropool_wasm::has_prover(), cfg!(feature = "prover"));
+}
+
+#[wasm_bindgen_test]
+fn self_test() {
+    let report = libzeropool_wasm::self_test();
+
+    assert!(report.address_derivation);
+    assert!(report.address_roundtrip);
+    assert!(report.merkle_proof);
+    assert!(report.passed());
+
+    let skipped: Vec<_> = report
+        .skipped_steps()
+        .iter()
+        .map(|step| step.as_string().unwrap())
+        .collect();
+    assert_eq!(skipped, vec!["noteEncryption"]);
+}